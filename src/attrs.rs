@@ -10,6 +10,12 @@ use core::{hash::Hash, ops::Range};
 pub use fontdb::{Family, Stretch, Style, Weight};
 use rangemap::RangeMap;
 
+/// Pack a 4-byte OpenType feature tag (e.g. `feature_tag(b"liga")`) into a `u32`
+#[inline]
+pub const fn feature_tag(tag: &[u8; 4]) -> u32 {
+    ((tag[0] as u32) << 24) | ((tag[1] as u32) << 16) | ((tag[2] as u32) << 8) | (tag[3] as u32)
+}
+
 /// Text color
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Color(pub u32);
@@ -87,6 +93,55 @@ impl FamilyOwned {
     }
 }
 
+/// Font metrics needed to place decoration lines, in font design units
+/// (analogous to the `underline_size`/`underline_offset`/`strikeout_size`/
+/// `strikeout_offset` exposed by `ttf_parser::Face` and similar font-metrics types)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FontMetrics {
+    pub units_per_em: u16,
+    pub underline_position: i16,
+    pub underline_thickness: i16,
+    pub strikeout_position: i16,
+    pub strikeout_thickness: i16,
+    /// `false` if the face provides no strikeout metrics
+    pub has_strikeout: bool,
+    pub x_height: i16,
+    pub ascender: i16,
+}
+
+/// A horizontal decoration line (underline/strikethrough/overline), scaled to
+/// `font_size` and placed relative to the baseline (positive `y` is upward)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecorationLine {
+    pub y: f32,
+    pub thickness: f32,
+    pub color: Color,
+}
+
+/// Per-run emphasis flags a renderer should fake because no face in the family
+/// matched the requested [`Weight`]/[`Style`] exactly
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Synthesis {
+    /// Apply outline emboldening to approximate the requested weight
+    pub synthetic_bold: bool,
+    /// Horizontal shear factor to approximate an italic/oblique style, or
+    /// `0.0` if no shear is needed
+    pub synthetic_oblique_skew: f32,
+}
+
+impl Synthesis {
+    /// No synthetic emphasis is needed
+    pub const NONE: Self = Self {
+        synthetic_bold: false,
+        synthetic_oblique_skew: 0.0,
+    };
+
+    /// True if neither faux-bold nor faux-italic emphasis is requested
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
 /// Text attributes
 #[derive(Clone, Copy, Debug)]
 pub struct Attrs<'a> {
@@ -98,6 +153,14 @@ pub struct Attrs<'a> {
     pub weight: Weight,
     pub scaling: f32,
     pub metadata: usize,
+    pub features: &'a [(u32, u32)],
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub overline: bool,
+    pub decoration_color_opt: Option<Color>,
+    pub letter_spacing: f32,
+    pub word_spacing: f32,
+    pub allow_synthetic: bool,
 }
 
 impl PartialEq for Attrs<'_> {
@@ -111,6 +174,14 @@ impl PartialEq for Attrs<'_> {
             weight,
             scaling,
             metadata,
+            features,
+            underline,
+            strikethrough,
+            overline,
+            decoration_color_opt,
+            letter_spacing,
+            word_spacing,
+            allow_synthetic,
         } = self;
 
         *color_opt == other.color_opt
@@ -120,6 +191,14 @@ impl PartialEq for Attrs<'_> {
             && *weight == other.weight
             && f32::total_cmp(scaling, &other.scaling).is_eq()
             && *metadata == other.metadata
+            && *features == other.features
+            && *underline == other.underline
+            && *strikethrough == other.strikethrough
+            && *overline == other.overline
+            && *decoration_color_opt == other.decoration_color_opt
+            && f32::total_cmp(letter_spacing, &other.letter_spacing).is_eq()
+            && f32::total_cmp(word_spacing, &other.word_spacing).is_eq()
+            && *allow_synthetic == other.allow_synthetic
     }
 }
 impl Eq for Attrs<'_> {}
@@ -134,6 +213,14 @@ impl Hash for Attrs<'_> {
             weight,
             scaling,
             metadata,
+            features,
+            underline,
+            strikethrough,
+            overline,
+            decoration_color_opt,
+            letter_spacing,
+            word_spacing,
+            allow_synthetic,
         } = self;
 
         color_opt.hash(state);
@@ -143,6 +230,14 @@ impl Hash for Attrs<'_> {
         weight.hash(state);
         scaling.to_bits().hash(state);
         metadata.hash(state);
+        features.hash(state);
+        underline.hash(state);
+        strikethrough.hash(state);
+        overline.hash(state);
+        decoration_color_opt.hash(state);
+        letter_spacing.to_bits().hash(state);
+        word_spacing.to_bits().hash(state);
+        allow_synthetic.hash(state);
     }
 }
 
@@ -159,6 +254,14 @@ impl<'a> Attrs<'a> {
             weight: Weight::NORMAL,
             scaling: 1.0,
             metadata: 0,
+            features: &[],
+            underline: false,
+            strikethrough: false,
+            overline: false,
+            decoration_color_opt: None,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            allow_synthetic: true,
         }
     }
 
@@ -168,6 +271,64 @@ impl<'a> Attrs<'a> {
         self
     }
 
+    /// Set underline
+    pub fn underline(mut self, underline: bool) -> Self {
+        self.underline = underline;
+        self
+    }
+
+    /// Set strikethrough
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+        self
+    }
+
+    /// Set overline
+    pub fn overline(mut self, overline: bool) -> Self {
+        self.overline = overline;
+        self
+    }
+
+    /// Set decoration [Color]
+    pub fn decoration_color(mut self, color: Color) -> Self {
+        self.decoration_color_opt = Some(color);
+        self
+    }
+
+    /// Set letter spacing
+    pub fn letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.letter_spacing = letter_spacing;
+        self
+    }
+
+    /// Set word spacing
+    pub fn word_spacing(mut self, word_spacing: f32) -> Self {
+        self.word_spacing = word_spacing;
+        self
+    }
+
+    /// Set whether synthetic bold/oblique emulation is allowed (default `true`)
+    pub fn allow_synthetic(mut self, allow_synthetic: bool) -> Self {
+        self.allow_synthetic = allow_synthetic;
+        self
+    }
+
+    /// Add this span's letter and word spacing to glyph advances
+    ///
+    /// `letter_spacing` is added to every advance; `word_spacing` is added
+    /// additionally to the advance following a breakable space, i.e. where the
+    /// corresponding entry of `after_breakable_space` is `true`. This crate has
+    /// no shaping pass of its own; a caller shaping this span is expected to
+    /// call this on the run's advances itself.
+    pub fn apply_spacing(&self, advances: &mut [f32], after_breakable_space: &[bool]) {
+        for (advance, &after_space) in advances.iter_mut().zip(after_breakable_space) {
+            *advance += self.letter_spacing;
+            if after_space {
+                *advance += self.word_spacing;
+            }
+        }
+    }
+
     /// Set [Family]
     pub fn family(mut self, family: Family<'a>) -> Self {
         self.family = family;
@@ -204,6 +365,89 @@ impl<'a> Attrs<'a> {
         self
     }
 
+    /// Set OpenType feature `(tag, value)` pairs
+    pub fn features(mut self, features: &'a [(u32, u32)]) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Get the decoration [Color], falling back to [`color_opt`](Self::color_opt)
+    pub fn decoration_color_or(&self, default: Color) -> Color {
+        self.decoration_color_opt
+            .or(self.color_opt)
+            .unwrap_or(default)
+    }
+
+    /// Compute this span's underline/strikethrough/overline lines, scaled from
+    /// `metrics` to `font_size`
+    ///
+    /// Where `metrics` has no strikeout table (`has_strikeout` is `false`), the
+    /// strikeout position is synthesized from the x-height instead. This crate
+    /// has no layout module of its own; a caller laying out glyphs is expected
+    /// to call this per run and draw the returned lines alongside them.
+    pub fn decoration_lines(&self, metrics: FontMetrics, font_size: f32) -> Vec<DecorationLine> {
+        let mut lines = Vec::new();
+        if !(self.underline || self.strikethrough || self.overline) {
+            return lines;
+        }
+
+        let color = self.decoration_color_or(self.color_opt.unwrap_or(Color::rgb(0, 0, 0)));
+        let units_per_em = metrics.units_per_em.max(1) as f32;
+        let scale = font_size / units_per_em;
+
+        if self.underline {
+            lines.push(DecorationLine {
+                y: metrics.underline_position as f32 * scale,
+                thickness: metrics.underline_thickness as f32 * scale,
+                color,
+            });
+        }
+
+        if self.strikethrough {
+            let (position, thickness) = if metrics.has_strikeout {
+                (metrics.strikeout_position, metrics.strikeout_thickness)
+            } else {
+                // no strikeout table: place the line at half the x-height, with
+                // the underline's thickness as a sensible default
+                (metrics.x_height / 2, metrics.underline_thickness)
+            };
+            lines.push(DecorationLine {
+                y: position as f32 * scale,
+                thickness: thickness as f32 * scale,
+                color,
+            });
+        }
+
+        if self.overline {
+            lines.push(DecorationLine {
+                y: metrics.ascender as f32 * scale,
+                thickness: metrics.underline_thickness as f32 * scale,
+                color,
+            });
+        }
+
+        lines
+    }
+
+    /// Build the [`rustybuzz::Feature`] entries for this span's `features`, applied
+    /// over cluster range `range` of the shaped run
+    ///
+    /// This crate has no shaping pass of its own; a caller shaping text with
+    /// `rustybuzz` is expected to call this per run and pass the result to
+    /// its own shaping call.
+    pub fn rustybuzz_features(&self, range: Range<u32>) -> Vec<rustybuzz::Feature> {
+        self.features
+            .iter()
+            .map(|&(tag, value)| {
+                rustybuzz::Feature::new(
+                    rustybuzz::ttf_parser::Tag(tag),
+                    value,
+                    range.start as usize..range.end as usize,
+                )
+            })
+            .collect()
+    }
+
     /// Check if font matches
     pub fn matches(&self, face: &fontdb::FaceInfo) -> bool {
         //TODO: smarter way of including emoji
@@ -213,6 +457,49 @@ impl<'a> Attrs<'a> {
                 && face.stretch == self.stretch)
     }
 
+    /// Compute the synthetic emphasis needed to approximate this span's style and
+    /// weight when shaped with `face`
+    ///
+    /// Returns [`Synthesis::NONE`] when `face` already [matches](Self::matches),
+    /// or when [`allow_synthetic`](Self::allow_synthetic) is `false`.
+    pub fn synthesis(&self, face: &fontdb::FaceInfo) -> Synthesis {
+        if !self.allow_synthetic || self.matches(face) {
+            return Synthesis::NONE;
+        }
+
+        let synthetic_bold = self.weight.0 > face.weight.0;
+        let synthetic_oblique_skew = match (self.style, face.style) {
+            (Style::Italic | Style::Oblique, Style::Normal) => 0.25,
+            _ => 0.0,
+        };
+
+        Synthesis {
+            synthetic_bold,
+            synthetic_oblique_skew,
+        }
+    }
+
+    /// Pick the best face in `faces` for this span, preferring an exact
+    /// [match](Self::matches) and otherwise the closest weight/style, paired
+    /// with the [`Synthesis`] needed to approximate any remaining difference
+    pub fn best_match<'b>(
+        &self,
+        faces: impl IntoIterator<Item = &'b fontdb::FaceInfo>,
+    ) -> Option<(&'b fontdb::FaceInfo, Synthesis)> {
+        faces
+            .into_iter()
+            .min_by_key(|face| {
+                let style_distance = match (self.style, face.style) {
+                    (a, b) if a == b => 0,
+                    (Style::Italic, Style::Oblique) | (Style::Oblique, Style::Italic) => 1,
+                    _ => 2,
+                };
+                let weight_distance = (self.weight.0 as i32 - face.weight.0 as i32).unsigned_abs();
+                (!self.matches(face), style_distance, weight_distance)
+            })
+            .map(|face| (face, self.synthesis(face)))
+    }
+
     /// Check if this set of attributes can be shaped with another
     pub fn compatible(&self, other: &Self) -> bool {
         self.family == other.family
@@ -220,6 +507,9 @@ impl<'a> Attrs<'a> {
             && self.style == other.style
             && self.weight == other.weight
             && self.scaling.total_cmp(&other.scaling).is_eq()
+            && self.features == other.features
+            && self.letter_spacing.total_cmp(&other.letter_spacing).is_eq()
+            && self.word_spacing.total_cmp(&other.word_spacing).is_eq()
     }
 }
 
@@ -234,6 +524,15 @@ pub struct AttrsOwned {
     pub weight: Weight,
     pub scaling: f32,
     pub metadata: usize,
+    pub features: Vec<(u32, u32)>,
+    pub families_owned: Vec<FamilyOwned>,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub overline: bool,
+    pub decoration_color_opt: Option<Color>,
+    pub letter_spacing: f32,
+    pub word_spacing: f32,
+    pub allow_synthetic: bool,
 }
 
 impl AttrsOwned {
@@ -246,9 +545,43 @@ impl AttrsOwned {
             weight: attrs.weight,
             scaling: attrs.scaling,
             metadata: attrs.metadata,
+            features: attrs.features.to_vec(),
+            families_owned: Vec::new(),
+            underline: attrs.underline,
+            strikethrough: attrs.strikethrough,
+            overline: attrs.overline,
+            decoration_color_opt: attrs.decoration_color_opt,
+            letter_spacing: attrs.letter_spacing,
+            word_spacing: attrs.word_spacing,
+            allow_synthetic: attrs.allow_synthetic,
         }
     }
 
+    /// Set the ordered fallback [Family] chain, tried after the primary family
+    ///
+    /// This is the only place the chain is stored: `Attrs` can't carry a
+    /// borrowed chain across a round trip through `AttrsList`, so consumers
+    /// that need it (e.g. a shaper picking a fallback font) must consult
+    /// [`families`](Self::families) on the `AttrsOwned` directly, via
+    /// [`AttrsList::spans`](AttrsList::spans) or
+    /// [`AttrsList::defaults_owned`](AttrsList::defaults_owned), rather than
+    /// going through [`as_attrs`](Self::as_attrs).
+    pub fn with_families<'a>(mut self, families: impl IntoIterator<Item = Family<'a>>) -> Self {
+        self.families_owned = families.into_iter().map(FamilyOwned::new).collect();
+        self
+    }
+
+    /// The primary family plus its fallback chain, in order
+    pub fn families(&self) -> impl Iterator<Item = Family<'_>> + '_ {
+        core::iter::once(self.family_owned.as_family())
+            .chain(self.families_owned.iter().map(FamilyOwned::as_family))
+    }
+
+    /// Get a borrowed [`Attrs`] view of these attributes
+    ///
+    /// The fallback family chain is not part of `Attrs` (it cannot be
+    /// borrowed back out of owned storage without self-referencing), so
+    /// callers that need it should use [`families`](Self::families) instead.
     pub fn as_attrs(&self) -> Attrs {
         Attrs {
             color_opt: self.color_opt,
@@ -258,10 +591,24 @@ impl AttrsOwned {
             weight: self.weight,
             scaling: self.scaling,
             metadata: self.metadata,
+            features: &self.features,
+            underline: self.underline,
+            strikethrough: self.strikethrough,
+            overline: self.overline,
+            decoration_color_opt: self.decoration_color_opt,
+            letter_spacing: self.letter_spacing,
+            word_spacing: self.word_spacing,
+            allow_synthetic: self.allow_synthetic,
         }
     }
 }
 
+impl From<Attrs<'_>> for AttrsOwned {
+    fn from(attrs: Attrs<'_>) -> Self {
+        Self::new(attrs)
+    }
+}
+
 impl PartialEq for AttrsOwned {
     fn eq(&self, other: &Self) -> bool {
         // compile error if new fields are added
@@ -273,6 +620,15 @@ impl PartialEq for AttrsOwned {
             weight,
             scaling,
             metadata,
+            features,
+            families_owned,
+            underline,
+            strikethrough,
+            overline,
+            decoration_color_opt,
+            letter_spacing,
+            word_spacing,
+            allow_synthetic,
         } = self;
 
         *color_opt == other.color_opt
@@ -282,6 +638,15 @@ impl PartialEq for AttrsOwned {
             && *weight == other.weight
             && f32::total_cmp(scaling, &other.scaling).is_eq()
             && *metadata == other.metadata
+            && *features == other.features
+            && *families_owned == other.families_owned
+            && *underline == other.underline
+            && *strikethrough == other.strikethrough
+            && *overline == other.overline
+            && *decoration_color_opt == other.decoration_color_opt
+            && f32::total_cmp(letter_spacing, &other.letter_spacing).is_eq()
+            && f32::total_cmp(word_spacing, &other.word_spacing).is_eq()
+            && *allow_synthetic == other.allow_synthetic
     }
 }
 impl Eq for AttrsOwned {}
@@ -296,6 +661,15 @@ impl Hash for AttrsOwned {
             weight,
             scaling,
             metadata,
+            features,
+            families_owned,
+            underline,
+            strikethrough,
+            overline,
+            decoration_color_opt,
+            letter_spacing,
+            word_spacing,
+            allow_synthetic,
         } = self;
 
         color_opt.hash(state);
@@ -305,11 +679,19 @@ impl Hash for AttrsOwned {
         weight.hash(state);
         scaling.to_bits().hash(state);
         metadata.hash(state);
+        features.hash(state);
+        families_owned.hash(state);
+        underline.hash(state);
+        strikethrough.hash(state);
+        overline.hash(state);
+        decoration_color_opt.hash(state);
+        letter_spacing.to_bits().hash(state);
+        word_spacing.to_bits().hash(state);
+        allow_synthetic.hash(state);
     }
 }
 
 /// List of text attributes to apply to a line
-//TODO: have this clean up the spans when changes are made
 #[derive(Eq, PartialEq)]
 pub struct AttrsList {
     defaults: AttrsOwned,
@@ -317,10 +699,13 @@ pub struct AttrsList {
 }
 
 impl AttrsList {
-    /// Create a new attributes list with a set of default [Attrs]
-    pub fn new(defaults: Attrs) -> Self {
+    /// Create a new attributes list with a set of default attributes
+    ///
+    /// Accepts either [`Attrs`] or an [`AttrsOwned`] (e.g. one built with
+    /// [`AttrsOwned::with_families`] to give the defaults a fallback chain).
+    pub fn new(defaults: impl Into<AttrsOwned>) -> Self {
         Self {
-            defaults: AttrsOwned::new(defaults),
+            defaults: defaults.into(),
             spans: RangeMap::new(),
         }
     }
@@ -330,6 +715,26 @@ impl AttrsList {
         self.defaults.as_attrs()
     }
 
+    /// Get the default attributes, including their fallback family chain
+    pub fn defaults_owned(&self) -> &AttrsOwned {
+        &self.defaults
+    }
+
+    /// Set the default attributes, removing any spans that are now redundant
+    /// (i.e. that are now equal to the new defaults)
+    pub fn set_defaults(&mut self, defaults: impl Into<AttrsOwned>) {
+        self.defaults = defaults.into();
+        let redundant: Vec<Range<usize>> = self
+            .spans
+            .iter()
+            .filter(|(_, attrs)| **attrs == self.defaults)
+            .map(|(range, _)| range.clone())
+            .collect();
+        for range in redundant {
+            self.spans.remove(range);
+        }
+    }
+
     /// Get the current attribute spans
     pub fn spans(&self) -> Vec<(&Range<usize>, &AttrsOwned)> {
         self.spans.iter().collect()
@@ -341,13 +746,25 @@ impl AttrsList {
     }
 
     /// Add an attribute span, removes any previous matching parts of spans
-    pub fn add_span(&mut self, range: Range<usize>, attrs: Attrs) {
+    ///
+    /// Adjacent spans that end up carrying equal attributes are merged
+    /// automatically, and a span that becomes equal to the defaults is
+    /// dropped rather than stored, keeping the span map minimal. Accepts
+    /// either [`Attrs`] or an [`AttrsOwned`] (e.g. one built with
+    /// [`AttrsOwned::with_families`] to give this span a fallback chain).
+    pub fn add_span(&mut self, range: Range<usize>, attrs: impl Into<AttrsOwned>) {
         //do not support 1..1 even if by accident.
         if range.start == range.end {
             return;
         }
 
-        self.spans.insert(range, AttrsOwned::new(attrs));
+        let attrs_owned = attrs.into();
+        if attrs_owned == self.defaults {
+            self.spans.remove(range);
+        } else {
+            // RangeMap::insert coalesces with adjacent entries of equal value
+            self.spans.insert(range, attrs_owned);
+        }
     }
 
     /// Get the attribute span for an index
@@ -360,9 +777,38 @@ impl AttrsList {
             .unwrap_or(self.defaults.as_attrs())
     }
 
+    /// Iterate over gap-free attribute runs covering `0..len`
+    ///
+    /// Any gap between spans (or before the first/after the last span) is
+    /// filled with [`defaults`](Self::defaults), so shaping code can walk
+    /// contiguous style runs without separately reconciling [`get_span`](Self::get_span)
+    /// calls against the defaults.
+    pub fn attrs_runs(&self, len: usize) -> impl Iterator<Item = (Range<usize>, Attrs)> + '_ {
+        let mut runs = Vec::new();
+        let mut pos = 0;
+        for (range, attrs) in self.spans.iter() {
+            if pos >= len {
+                break;
+            }
+            let start = range.start.clamp(pos, len);
+            let end = range.end.min(len);
+            if start > pos {
+                runs.push((pos..start, self.defaults.as_attrs()));
+            }
+            if end > start {
+                runs.push((start..end, attrs.as_attrs()));
+            }
+            pos = pos.max(end);
+        }
+        if pos < len {
+            runs.push((pos..len, self.defaults.as_attrs()));
+        }
+        runs.into_iter()
+    }
+
     /// Split attributes list at an offset
     pub fn split_off(&mut self, index: usize) -> Self {
-        let mut new = Self::new(self.defaults.as_attrs());
+        let mut new = Self::new(self.defaults.clone());
         let mut removes = Vec::new();
 
         //get the keys we need to remove or fix.
@@ -395,3 +841,233 @@ impl AttrsList {
         new
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rustybuzz_features_maps_tags_and_cluster_range() {
+        let liga = feature_tag(b"liga");
+        let smcp = feature_tag(b"smcp");
+        let feature_list = [(liga, 0), (smcp, 1)];
+        let attrs = Attrs::new().features(&feature_list);
+
+        let features = attrs.rustybuzz_features(2..5);
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].tag, rustybuzz::ttf_parser::Tag(liga));
+        assert_eq!(features[0].value, 0);
+        assert_eq!(features[1].tag, rustybuzz::ttf_parser::Tag(smcp));
+        assert_eq!(features[1].value, 1);
+        for feature in &features {
+            assert_eq!((feature.start, feature.end), (2, 5));
+        }
+    }
+
+    #[test]
+    fn attrs_owned_families_includes_primary_family_first() {
+        let attrs = Attrs::new().family(Family::Name("Fira Code"));
+        let owned =
+            AttrsOwned::new(attrs).with_families([Family::Monospace, Family::SansSerif]);
+
+        let chain: Vec<Family> = owned.families().collect();
+        assert_eq!(
+            chain,
+            vec![Family::Name("Fira Code"), Family::Monospace, Family::SansSerif]
+        );
+    }
+
+    #[test]
+    fn attrs_list_span_families_survive_storage_but_not_as_attrs() {
+        let mut list = AttrsList::new(Attrs::new());
+        let span_attrs =
+            AttrsOwned::new(Attrs::new().weight(Weight::BOLD)).with_families([Family::Monospace]);
+        list.add_span(0..5, span_attrs);
+
+        // `spans()`/`defaults_owned()` expose the full fallback chain, since
+        // they hand back `AttrsOwned` directly
+        let stored = list.spans();
+        let (_, attrs_owned) = stored
+            .into_iter()
+            .find(|(range, _)| **range == (0..5))
+            .expect("span was stored");
+        let chain: Vec<Family> = attrs_owned.families().collect();
+        assert_eq!(chain, vec![Family::SansSerif, Family::Monospace]);
+
+        // `get_span`/`attrs_runs` hand back a borrowed `Attrs`, which never
+        // carries a fallback chain; callers that need one must go through
+        // `spans()`/`defaults_owned()` as above
+        let run_owned = list
+            .attrs_runs(5)
+            .find(|(range, _)| *range == (0..5))
+            .map(|(_, attrs)| AttrsOwned::new(attrs));
+        let run_families: Vec<Family> = run_owned
+            .as_ref()
+            .map(|owned| owned.families().collect())
+            .unwrap_or_default();
+        assert_eq!(run_families, vec![Family::SansSerif]);
+    }
+
+    #[test]
+    fn decoration_lines_scale_by_font_size() {
+        let metrics = FontMetrics {
+            units_per_em: 1000,
+            underline_position: -100,
+            underline_thickness: 50,
+            strikeout_position: 300,
+            strikeout_thickness: 60,
+            has_strikeout: true,
+            x_height: 500,
+            ascender: 800,
+        };
+        let attrs = Attrs::new().underline(true).strikethrough(true);
+
+        let lines = attrs.decoration_lines(metrics, 20.0);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].y, -2.0);
+        assert_eq!(lines[0].thickness, 1.0);
+        assert_eq!(lines[1].y, 6.0);
+        assert!((lines[1].thickness - 1.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn decoration_lines_synthesizes_strikeout_from_x_height() {
+        let metrics = FontMetrics {
+            units_per_em: 1000,
+            underline_position: -100,
+            underline_thickness: 50,
+            strikeout_position: 0,
+            strikeout_thickness: 0,
+            has_strikeout: false,
+            x_height: 500,
+            ascender: 800,
+        };
+        let attrs = Attrs::new().strikethrough(true);
+
+        let lines = attrs.decoration_lines(metrics, 10.0);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].y, 2.5);
+        assert_eq!(lines[0].thickness, 0.5);
+    }
+
+    #[test]
+    fn decoration_lines_places_overline_at_ascender() {
+        let metrics = FontMetrics {
+            units_per_em: 1000,
+            underline_position: -100,
+            underline_thickness: 50,
+            strikeout_position: 300,
+            strikeout_thickness: 60,
+            has_strikeout: true,
+            x_height: 500,
+            ascender: 800,
+        };
+        let attrs = Attrs::new().overline(true);
+
+        let lines = attrs.decoration_lines(metrics, 20.0);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].y, 16.0);
+        assert_eq!(lines[0].thickness, 1.0);
+    }
+
+    #[test]
+    fn apply_spacing_adds_letter_and_word_spacing() {
+        let attrs = Attrs::new().letter_spacing(1.0).word_spacing(4.0);
+        let mut advances = [10.0, 10.0, 10.0];
+        let after_breakable_space = [false, true, false];
+
+        attrs.apply_spacing(&mut advances, &after_breakable_space);
+
+        assert_eq!(advances, [11.0, 15.0, 11.0]);
+    }
+
+    #[test]
+    fn add_span_merges_adjacent_equal_spans() {
+        let mut list = AttrsList::new(Attrs::new());
+        let bold = Attrs::new().weight(Weight::BOLD);
+
+        list.add_span(0..5, bold);
+        list.add_span(5..10, bold);
+
+        let spans = list.spans();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(*spans[0].0, 0..10);
+    }
+
+    #[test]
+    fn add_span_equal_to_defaults_is_not_stored() {
+        let defaults = Attrs::new();
+        let mut list = AttrsList::new(defaults);
+
+        list.add_span(0..5, Attrs::new().weight(Weight::BOLD));
+        assert_eq!(list.spans().len(), 1);
+
+        list.add_span(0..5, defaults);
+        assert_eq!(list.spans().len(), 0);
+    }
+
+    #[test]
+    fn set_defaults_prunes_now_redundant_spans() {
+        let mut list = AttrsList::new(Attrs::new());
+        let bold = Attrs::new().weight(Weight::BOLD);
+        list.add_span(0..5, bold);
+        list.add_span(5..10, Attrs::new().weight(Weight::LIGHT));
+
+        list.set_defaults(bold);
+
+        let spans = list.spans();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(*spans[0].0, 5..10);
+    }
+
+    #[test]
+    fn attrs_runs_fills_gaps_with_defaults() {
+        let mut list = AttrsList::new(Attrs::new());
+        let bold = Attrs::new().weight(Weight::BOLD);
+        list.add_span(3..6, bold);
+
+        let runs: Vec<(Range<usize>, Weight)> = list
+            .attrs_runs(10)
+            .map(|(range, attrs)| (range, attrs.weight))
+            .collect();
+
+        assert_eq!(
+            runs,
+            vec![
+                (0..3, Weight::NORMAL),
+                (3..6, Weight::BOLD),
+                (6..10, Weight::NORMAL),
+            ]
+        );
+    }
+
+    #[test]
+    fn attrs_runs_on_empty_list_yields_one_default_run() {
+        let list = AttrsList::new(Attrs::new());
+        let runs: Vec<Range<usize>> = list.attrs_runs(4).map(|(range, _)| range).collect();
+        assert_eq!(runs, vec![0..4]);
+    }
+
+    #[test]
+    fn attrs_runs_clips_spans_past_len() {
+        let mut list = AttrsList::new(Attrs::new());
+        list.add_span(2..100, Attrs::new().weight(Weight::BOLD));
+
+        let runs: Vec<Range<usize>> = list.attrs_runs(5).map(|(range, _)| range).collect();
+        assert_eq!(runs, vec![0..2, 2..5]);
+    }
+
+    #[test]
+    fn synthesis_none_has_no_synthetic_emphasis() {
+        let synthesis = Synthesis::NONE;
+        assert!(!synthesis.synthetic_bold);
+        assert_eq!(synthesis.synthetic_oblique_skew, 0.0);
+        assert!(synthesis.is_none());
+
+        let bold = Synthesis {
+            synthetic_bold: true,
+            synthetic_oblique_skew: 0.0,
+        };
+        assert!(!bold.is_none());
+    }
+}